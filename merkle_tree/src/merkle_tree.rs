@@ -1,7 +1,23 @@
 extern crate crypto;
 
-use crypto::digest::Digest;
-use crypto::sha3::Sha3;
+mod db;
+mod hash256;
+mod hasher;
+mod hex;
+mod multiproof;
+mod proof;
+mod sparse;
+
+use std::collections::HashSet;
+use std::convert::TryInto;
+use std::marker::PhantomData;
+
+pub use db::{Db, DiskDb, MemoryDb, NodeType};
+pub use hash256::{Hash256, ParseError};
+pub use hasher::{Hasher, Keccak256, Sha256};
+pub use multiproof::MultiProof;
+pub use proof::Proof;
+pub use sparse::{KeyCollision, SparseMerkleTree, SparseProof, Terminal, EMPTY_NODE};
 
 #[derive(Debug, PartialEq)]
 pub enum CreationError {
@@ -9,21 +25,95 @@ pub enum CreationError {
     Empty,
 }
 
+/// The root of a tree holding zero leaves, used so `levels` is never empty
+/// even for a tree built via `new()` that hasn't had any leaves added yet.
+const EMPTY_ROOT: [u8; 32] = [0; 32];
+
 #[derive(Debug, PartialEq)]
-pub struct MerkleTree {
+pub struct MerkleTree<H: Hasher = Keccak256, D: Db = MemoryDb> {
     leaves: Vec<[u8; 32]>,
+    /// `levels[0]` is the power-of-two padded leaf layer; `levels[levels.len() - 1]`
+    /// is the single-hash root layer. Kept in sync with `leaves` lazily, in
+    /// `recompute`, so repeated calls to `get_root` don't rebuild the tree.
+    levels: Vec<Vec<[u8; 32]>>,
+    /// Indices (into `levels[0]`) touched since the last `recompute`.
+    dirty: HashSet<usize>,
+    /// Content-addressed backing store nodes are persisted to as they're
+    /// computed, so a tree can be reopened later from just its root hash.
+    db: D,
+    _hasher: PhantomData<H>,
 }
-impl MerkleTree {
-    pub fn build_from(array: Vec<String>) -> Result<MerkleTree, CreationError> {
+impl<H: Hasher, D: Db> MerkleTree<H, D> {
+    pub fn build_from(array: Vec<String>) -> Result<MerkleTree<H, D>, CreationError>
+    where
+        D: Default,
+    {
         if array.is_empty() {
             return Err(CreationError::Empty);
         }
         let leaves = Self::get_leaves(&array);
-        Ok(MerkleTree { leaves })
+        let levels = Self::build_levels(&Self::resize_leaves(&leaves));
+        let mut tree = MerkleTree {
+            leaves,
+            levels,
+            dirty: HashSet::new(),
+            db: D::default(),
+            _hasher: PhantomData,
+        };
+        tree.persist_all_levels();
+        Ok(tree)
+    }
+
+    /// Starts an empty tree backed by `db`, ready to grow via `add_leaves`.
+    pub fn new(db: D) -> MerkleTree<H, D> {
+        MerkleTree {
+            leaves: vec![],
+            levels: Self::build_levels(&Self::resize_leaves(&vec![])),
+            dirty: HashSet::new(),
+            db,
+            _hasher: PhantomData,
+        }
+    }
+
+    /// Reopens a tree from just its persisted root hash, walking `db` down
+    /// from `root` to recover every leaf. Returns `None` if `db` is missing
+    /// a node on the way down, or if the reconstructed tree doesn't hash
+    /// back to `root`.
+    pub fn load(db: D, root: [u8; 32]) -> Option<MerkleTree<H, D>> {
+        let mut leaves = vec![];
+        Self::collect_leaves(&db, root, &mut leaves)?;
+        let levels = Self::build_levels(&leaves);
+        if levels.last()?.first()? != &root {
+            return None;
+        }
+        Some(MerkleTree {
+            leaves,
+            levels,
+            dirty: HashSet::new(),
+            db,
+            _hasher: PhantomData,
+        })
+    }
+
+    fn collect_leaves(db: &D, key: [u8; 32], out: &mut Vec<[u8; 32]>) -> Option<()> {
+        let (node_type, _payload_length, bytes) = db.get(&key)?;
+        match node_type {
+            NodeType::Leaf => {
+                out.push(key);
+                Some(())
+            }
+            NodeType::Internal => {
+                let left: [u8; 32] = bytes.get(0..32)?.try_into().ok()?;
+                let right: [u8; 32] = bytes.get(32..64)?.try_into().ok()?;
+                Self::collect_leaves(db, left, out)?;
+                Self::collect_leaves(db, right, out)
+            }
+        }
     }
 
-    pub fn get_root(&self) -> [u8;32] {
-        Self::calculate_root(&Self::resize_leaves(&self.leaves))
+    pub fn get_root(&mut self) -> [u8;32] {
+        self.recompute();
+        self.levels.last().unwrap()[0]
     }
     pub fn count_leaves(&self) -> usize {
         self.leaves.len()
@@ -31,36 +121,149 @@ impl MerkleTree {
 
     pub fn add_leaves(&mut self, new_leaves: Vec<String>) {
         let new_leaves = Self::get_leaves(&new_leaves);
-        self.leaves.extend(new_leaves);
+        let start = self.leaves.len();
+        self.leaves.extend(new_leaves.iter().copied());
+        for hash in new_leaves {
+            self.persist_leaf(hash);
+        }
+        self.dirty.extend(start..self.leaves.len());
     }
 
-    /// Checks if a leaf with the given hash exists in the Merkle tree and returns its proof and index.
-    pub fn contains_leaf(&mut self, leaf_hash: [u8;32]) -> Option<(Vec<[u8;32]>, usize)> {
-        if let Some(index) = self.leaves.iter().position(|x| *x == leaf_hash) {
-            Some((
+    /// Brings `levels` back in sync with `leaves`, recomputing only the
+    /// nodes on the path from each dirty leaf up to the root.
+    ///
+    /// If the leaf count crossed a power-of-two boundary the tree gains a
+    /// level and the whole tree is rebuilt from scratch instead. Otherwise
+    /// the padded width is unchanged, but the trailing padding duplicate(s)
+    /// may still have shifted onto indices `add_leaves` never marked dirty
+    /// (e.g. 5 leaves growing to 6, both padded to 8) — diff the old and new
+    /// leaf layers to catch those too.
+    fn recompute(&mut self) {
+        if self.dirty.is_empty() {
+            return;
+        }
+        let resized = Self::resize_leaves(&self.leaves);
+        if self.levels.is_empty() || resized.len() != self.levels[0].len() {
+            self.levels = Self::build_levels(&resized);
+            self.persist_all_levels();
+            self.dirty.clear();
+            return;
+        }
+        let mut dirty: HashSet<usize> = self.dirty.drain().collect();
+        for (index, (old, new)) in self.levels[0].iter().zip(resized.iter()).enumerate() {
+            if old != new {
+                dirty.insert(index);
+            }
+        }
+        self.levels[0] = resized;
+
+        let mut dirty: Vec<usize> = dirty.into_iter().collect();
+        for level in 0..self.levels.len() - 1 {
+            let mut parents_dirty = HashSet::new();
+            for index in dirty {
+                let parent_index = index / 2;
+                let left = self.levels[level][parent_index * 2];
+                let right = self.levels[level][parent_index * 2 + 1];
+                let new_hash = H::hash(&[left, right].concat());
+                if self.levels[level + 1][parent_index] != new_hash {
+                    self.levels[level + 1][parent_index] = new_hash;
+                    parents_dirty.insert(parent_index);
+                    self.persist_internal(new_hash, left, right);
+                }
+            }
+            if parents_dirty.is_empty() {
+                break;
+            }
+            dirty = parents_dirty.into_iter().collect();
+        }
+    }
+
+    /// Builds every level of a tree bottom-up from an already power-of-two
+    /// padded leaf layer.
+    fn build_levels(leaves: &[[u8; 32]]) -> Vec<Vec<[u8; 32]>> {
+        let mut levels = vec![leaves.to_vec()];
+        while levels.last().unwrap().len() > 1 {
+            let mut parents = vec![];
+            for chunk in levels.last().unwrap().chunks(2) {
+                parents.push(H::hash(&[chunk[0], chunk[1]].concat()));
+            }
+            levels.push(parents);
+        }
+        levels
+    }
+
+    fn persist_leaf(&mut self, hash: [u8; 32]) {
+        self.db.insert(hash, NodeType::Leaf, hash.len(), &hash);
+    }
+
+    fn persist_internal(&mut self, hash: [u8; 32], left: [u8; 32], right: [u8; 32]) {
+        let bytes = [left, right].concat();
+        self.db.insert(hash, NodeType::Internal, bytes.len(), &bytes);
+    }
+
+    /// Writes every node currently in `levels` to `db`, used after a full
+    /// rebuild where nodes may have changed without going through
+    /// `recompute`'s incremental path.
+    fn persist_all_levels(&mut self) {
+        let leaf_hashes = self.levels[0].clone();
+        for hash in leaf_hashes {
+            self.persist_leaf(hash);
+        }
+        for level in 0..self.levels.len() - 1 {
+            for parent_index in 0..self.levels[level + 1].len() {
+                let left = self.levels[level][parent_index * 2];
+                let right = self.levels[level][parent_index * 2 + 1];
+                let hash = self.levels[level + 1][parent_index];
+                self.persist_internal(hash, left, right);
+            }
+        }
+    }
+
+    /// Checks if a leaf with the given hash exists in the Merkle tree and returns its proof.
+    pub fn contains_leaf(&mut self, leaf_hash: [u8;32]) -> Option<Proof> {
+        self.leaves.iter().position(|x| *x == leaf_hash).map(|index| {
+            Proof::new(
                 Self::generate_proof(index, &Self::resize_leaves(&self.leaves), vec![]),
                 index,
-            ))
-        } else {
-            None
+            )
+        })
+    }
+
+    /// Proves several leaves against the root at once, sharing whatever
+    /// sibling hashes they have in common instead of repeating them per leaf.
+    ///
+    /// Returns `None` if any of `indices` is out of range for the (padded)
+    /// leaf layer, rather than panicking on a caller-supplied index.
+    pub fn generate_multiproof(&self, indices: &[usize]) -> Option<MultiProof> {
+        let leaves = Self::resize_leaves(&self.leaves);
+        if indices.iter().any(|&index| index >= leaves.len()) {
+            return None;
         }
+        Some(MultiProof::build(&leaves, indices, |left, right| {
+            H::hash(&[left, right].concat())
+        }))
     }
 
-    pub fn verify(
-        proof: Vec<[u8;32]>,
-        root: [u8;32],
-        mut leaf_hash: [u8;32],
-        mut leaf_index: usize,
-    ) -> bool {
-        for hash in proof {
+    /// Verifies a `MultiProof` for `leaves` (the claimed index -> hash pairs)
+    /// against `root`.
+    pub fn verify_multiproof(multiproof: &MultiProof, root: [u8;32], leaves: &[(usize, [u8;32])]) -> bool {
+        match multiproof.replay(leaves, |left, right| H::hash(&[left, right].concat())) {
+            Some(computed_root) => constant_time_eq(&computed_root, &root),
+            None => false,
+        }
+    }
+
+    pub fn verify(proof: &Proof, root: [u8;32], mut leaf_hash: [u8;32]) -> bool {
+        let mut leaf_index = proof.leaf_index;
+        for hash in proof.siblings() {
             if leaf_index % 2 == 0 {
-                leaf_hash = Self::calculate_hash(&[leaf_hash, hash].concat());
+                leaf_hash = H::hash(&[leaf_hash, hash].concat());
             } else {
-                leaf_hash = Self::calculate_hash(&[hash, leaf_hash].concat());
+                leaf_hash = H::hash(&[hash, leaf_hash].concat());
             }
             leaf_index /= 2;
         }
-        leaf_hash == root
+        constant_time_eq(&leaf_hash, &root)
     }
 
     fn generate_proof(leaf_index: usize, leaves: &[[u8;32]], mut proof: Vec<[u8;32]>) -> Vec<[u8;32]> {
@@ -78,9 +281,7 @@ impl MerkleTree {
         //build the parents array
         let mut parents_array = vec![];
         for chunk in leaves.chunks(2) {
-            parents_array.push(Self::calculate_hash(
-                &[chunk[0], chunk[1]].concat(),
-            ));
+            parents_array.push(H::hash(&[chunk[0], chunk[1]].concat()));
         }
         //get the next iteration index
         let new_index = leaf_index / 2;
@@ -91,13 +292,16 @@ impl MerkleTree {
     fn get_leaves(array: &[String]) -> Vec<[u8;32]> {
         let hashes: Vec<[u8;32]> = array
             .iter()
-            .map(|elem| Self::calculate_hash(elem.as_bytes()))
+            .map(|elem| H::hash(elem.as_bytes()))
             .collect();
 
         hashes
     }
 
     fn resize_leaves(leaves: &Vec<[u8;32]>) -> Vec<[u8;32]> {
+        if leaves.is_empty() {
+            return vec![EMPTY_ROOT];
+        }
         let mut resized_leaves = leaves.to_owned();
         let mut len = resized_leaves.len();
         while (len & (len - 1)) != 0 {
@@ -105,30 +309,18 @@ impl MerkleTree {
             len = resized_leaves.len();
         }
         resized_leaves
-    }   
-
-    fn calculate_root(array: &[[u8;32]]) -> [u8;32] {
-        if array.len() == 1 {
-            return *array.first().unwrap();
-        }
-        //build the parents array
-        let mut parents_array = vec![];
-        for chunk in array.chunks(2) {
-            parents_array.push(Self::calculate_hash(
-                &[chunk[0], chunk[1]].concat(),
-            ));
-        }
-
-        Self::calculate_root(&parents_array)
     }
+}
 
-    fn calculate_hash(input: &[u8]) -> [u8;32] {
-        let mut hasher = Sha3::keccak256();
-        hasher.input(input);
-        let mut output = [0; 32];
-        hasher.result(&mut output);
-        output
+/// Compares two hashes without branching on the position of the first
+/// differing byte, so an attacker timing `verify` can't narrow down where a
+/// forged root diverges from the real one.
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+    let mut diff = 0u8;
+    for i in 0..32 {
+        diff |= a[i] ^ b[i];
     }
+    diff == 0
 }
 
 #[cfg(test)]
@@ -137,22 +329,18 @@ mod tests {
     use super::*;
 
     fn calculate_hash(input: &[u8]) -> [u8;32] {
-        let mut hasher = Sha3::keccak256();
-        hasher.input(input);
-        let mut output = [0; 32];
-        hasher.result(&mut output);
-        output
+        Keccak256::hash(input)
     }
 
     #[test]
     fn build_from_empty_array() {
-        let tree = MerkleTree::build_from(vec![]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec![]);
         assert!(tree.is_err());
         assert_eq![tree, Err(CreationError::Empty)];
     }
     #[test]
     fn build_from_one_element_is_ok() {
-        let tree = MerkleTree::build_from(vec!["foo".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into()]);
         assert!(tree.is_ok());
         let tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 1];
@@ -161,8 +349,8 @@ mod tests {
     fn build_from_one_element_root_is_ok() {
         let root = calculate_hash("foo".as_bytes());
 
-        let tree = MerkleTree::build_from(vec!["foo".into()]);
-        let tree = tree.unwrap();
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into()]);
+        let mut tree = tree.unwrap();
 
         assert_eq![tree.count_leaves(), 1];
         assert_eq![tree.get_root(), root];
@@ -184,7 +372,7 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec![
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
             "foo".into(),
             "bar".into(),
             "hello".into(),
@@ -192,7 +380,7 @@ mod tests {
         ]);
 
         assert!(tree.is_ok());
-        let tree = tree.unwrap();
+        let mut tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 4];
         assert_eq![tree.get_root(), root];
     }
@@ -211,10 +399,10 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
 
         assert!(tree.is_ok());
-        let tree = tree.unwrap();
+        let mut tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 3];
         assert_eq![tree.get_root(), root];
     }
@@ -233,7 +421,7 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree with one element
-        let tree = MerkleTree::build_from(vec!["foo".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into()]);
         let mut tree = tree.unwrap();
 
         assert_eq![tree.count_leaves(), 1];
@@ -260,7 +448,7 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree with one element
-        let tree = MerkleTree::build_from(vec!["foo".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into()]);
         let mut tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 1];
         assert_eq![tree.get_root(), foo_hash];
@@ -278,6 +466,33 @@ mod tests {
         assert_eq![tree.get_root(), root];
     }
     #[test]
+    fn add_leaves_within_same_padded_width_matches_fresh_build() {
+        // 5 leaves pad to 8 (3 copies of leaf 4); adding a 6th still pads to
+        // 8 (2 copies of leaf 5), so the padding duplicates shift without
+        // the tree gaining a level.
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
+            "foo".into(),
+            "bar".into(),
+            "hello".into(),
+            "world!".into(),
+            "baz".into(),
+        ]);
+        let mut tree = tree.unwrap();
+        tree.add_leaves(vec!["qux".into()]);
+
+        let fresh = MerkleTree::<Keccak256>::build_from(vec![
+            "foo".into(),
+            "bar".into(),
+            "hello".into(),
+            "world!".into(),
+            "baz".into(),
+            "qux".into(),
+        ]);
+        let mut fresh = fresh.unwrap();
+
+        assert_eq![tree.get_root(), fresh.get_root()];
+    }
+    #[test]
     fn generate_proof_even_index() {
         //manually get the hashes of all inputs
         let foo_hash = calculate_hash("foo".as_bytes());
@@ -293,7 +508,7 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec![
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
             "foo".into(),
             "bar".into(),
             "hello".into(),
@@ -303,33 +518,17 @@ mod tests {
         assert_eq![tree.count_leaves(), 4];
         assert_eq![tree.get_root(), root];
 
-        let (proof, index) = tree.contains_leaf(hello_hash.clone()).unwrap();
-        assert_eq!(index, 2);
-        assert!(MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            hello_hash.clone(),
-            index
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
+        let proof = tree.contains_leaf(hello_hash.clone()).unwrap();
+        assert_eq!(proof.leaf_index, 2);
+        assert!(MerkleTree::<Keccak256>::verify(&proof, root.clone(), hello_hash.clone()));
+        assert!(!MerkleTree::<Keccak256>::verify(
+            &Proof { leaf_index: 3, ..proof.clone() },
             root.clone(),
             hello_hash,
-            3
         ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            bar_hash,
-            index
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            world_hash,
-            index
-        ));
-        assert!(!MerkleTree::verify(proof, root, foo_hash, index));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), bar_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), world_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root, foo_hash));
     }
     #[test]
     fn generate_proof_odd_index() {
@@ -347,7 +546,7 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec![
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
             "foo".into(),
             "bar".into(),
             "hello".into(),
@@ -357,33 +556,17 @@ mod tests {
         assert_eq![tree.count_leaves(), 4];
         assert_eq![tree.get_root(), root];
 
-        let (proof, index) = tree.contains_leaf(foo_hash.clone()).unwrap();
-        assert_eq!(index, 0);
-        assert!(MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            foo_hash.clone(),
-            index
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
+        let proof = tree.contains_leaf(foo_hash.clone()).unwrap();
+        assert_eq!(proof.leaf_index, 0);
+        assert!(MerkleTree::<Keccak256>::verify(&proof, root.clone(), foo_hash.clone()));
+        assert!(!MerkleTree::<Keccak256>::verify(
+            &Proof { leaf_index: 3, ..proof.clone() },
             root.clone(),
             foo_hash,
-            3
         ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            bar_hash,
-            index
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            world_hash,
-            index
-        ));
-        assert!(!MerkleTree::verify(proof, root, hello_hash, index));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), bar_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), world_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root, hello_hash));
     }
     #[test]
     fn generate_proof_three_leaves() {
@@ -400,32 +583,21 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
         let mut tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 3];
         assert_eq![tree.get_root(), root];
 
-        let (proof, index) = tree.contains_leaf(foo_hash.clone()).unwrap();
-        assert_eq!(index, 0);
-        assert!(MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            foo_hash.clone(),
-            index
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
+        let proof = tree.contains_leaf(foo_hash.clone()).unwrap();
+        assert_eq!(proof.leaf_index, 0);
+        assert!(MerkleTree::<Keccak256>::verify(&proof, root.clone(), foo_hash.clone()));
+        assert!(!MerkleTree::<Keccak256>::verify(
+            &Proof { leaf_index: 3, ..proof.clone() },
             root.clone(),
             foo_hash,
-            3
-        ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            bar_hash,
-            index
         ));
-        assert!(!MerkleTree::verify(proof, root, hello_hash, index));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), bar_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root, hello_hash));
     }
     #[test]
     fn generate_proof_three_leaves_at_index_two() {
@@ -442,30 +614,173 @@ mod tests {
         let root = calculate_hash(&[root1, root2].concat());
 
         //build the tree
-        let tree = MerkleTree::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into(), "bar".into(), "hello".into()]);
         let mut tree = tree.unwrap();
         assert_eq![tree.count_leaves(), 3];
         assert_eq![tree.get_root(), root];
 
-        let (proof, index) = tree.contains_leaf(hello_hash.clone()).unwrap();
-        assert_eq!(index, 2);
-        assert!(MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            hello_hash.clone(),
-            index
+        let proof = tree.contains_leaf(hello_hash.clone()).unwrap();
+        assert_eq!(proof.leaf_index, 2);
+        assert!(MerkleTree::<Keccak256>::verify(&proof, root.clone(), hello_hash.clone()));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root.clone(), foo_hash));
+        assert!(!MerkleTree::<Keccak256>::verify(&proof, root, bar_hash));
+    }
+
+    #[test]
+    fn multiproof_verifies_several_leaves_at_once() {
+        let foo_hash = calculate_hash("foo".as_bytes());
+        let bar_hash = calculate_hash("bar".as_bytes());
+        let hello_hash = calculate_hash("hello".as_bytes());
+        let world_hash = calculate_hash("world!".as_bytes());
+
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
+            "foo".into(),
+            "bar".into(),
+            "hello".into(),
+            "world!".into(),
+        ]);
+        let mut tree = tree.unwrap();
+        let root = tree.get_root();
+
+        let multiproof = tree.generate_multiproof(&[0, 2]).unwrap();
+        assert!(MerkleTree::<Keccak256>::verify_multiproof(
+            &multiproof,
+            root,
+            &[(0, foo_hash), (2, hello_hash)],
         ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            foo_hash,
-            2
+        assert!(!MerkleTree::<Keccak256>::verify_multiproof(
+            &multiproof,
+            root,
+            &[(0, bar_hash), (2, hello_hash)],
         ));
-        assert!(!MerkleTree::verify(
-            proof.clone(),
-            root.clone(),
-            bar_hash,
-            index
+        assert!(!MerkleTree::<Keccak256>::verify_multiproof(
+            &multiproof,
+            root,
+            &[(0, foo_hash), (2, world_hash)],
+        ));
+    }
+
+    #[test]
+    fn multiproof_verifies_all_leaves() {
+        let foo_hash = calculate_hash("foo".as_bytes());
+        let bar_hash = calculate_hash("bar".as_bytes());
+        let hello_hash = calculate_hash("hello".as_bytes());
+        let world_hash = calculate_hash("world!".as_bytes());
+
+        // A naturally 4-leaf tree, so requesting every leaf truly leaves no
+        // sibling undetermined (unlike 3 leaves, where slot 3 is still a
+        // padding duplicate of leaf 2 that a partial request wouldn't cover).
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
+            "foo".into(),
+            "bar".into(),
+            "hello".into(),
+            "world!".into(),
+        ]);
+        let mut tree = tree.unwrap();
+        let root = tree.get_root();
+
+        let multiproof = tree.generate_multiproof(&[0, 1, 2, 3]).unwrap();
+        assert!(multiproof.siblings.is_empty());
+        assert!(MerkleTree::<Keccak256>::verify_multiproof(
+            &multiproof,
+            root,
+            &[(0, foo_hash), (1, bar_hash), (2, hello_hash), (3, world_hash)],
+        ));
+    }
+
+    #[test]
+    fn multiproof_rejects_mismatched_leaf_count() {
+        let foo_hash = calculate_hash("foo".as_bytes());
+
+        let tree = MerkleTree::<Keccak256>::build_from(vec![
+            "foo".into(),
+            "bar".into(),
+            "hello".into(),
+            "world!".into(),
+        ]);
+        let mut tree = tree.unwrap();
+        let root = tree.get_root();
+
+        let multiproof = tree.generate_multiproof(&[0, 2]).unwrap();
+        assert!(!MerkleTree::<Keccak256>::verify_multiproof(
+            &multiproof,
+            root,
+            &[(0, foo_hash)],
         ));
     }
+
+    #[test]
+    fn generate_multiproof_rejects_out_of_range_index() {
+        let tree = MerkleTree::<Keccak256>::build_from(vec!["foo".into(), "bar".into()]);
+        let tree = tree.unwrap();
+
+        assert!(tree.generate_multiproof(&[5]).is_none());
+    }
+
+    #[test]
+    fn fresh_tree_get_root_does_not_panic() {
+        let mut tree: MerkleTree<Keccak256> = MerkleTree::new(MemoryDb::new());
+        assert_eq!(tree.get_root(), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn fresh_tree_generate_multiproof_on_empty_indices_does_not_panic() {
+        let tree: MerkleTree<Keccak256> = MerkleTree::new(MemoryDb::new());
+        let multiproof = tree.generate_multiproof(&[]).unwrap();
+        assert_eq!(multiproof.tree_width, 1);
+    }
+
+    #[test]
+    fn build_from_with_sha256_hasher_root_is_ok() {
+        let foo_hash = Sha256::hash("foo".as_bytes());
+        let bar_hash = Sha256::hash("bar".as_bytes());
+        let root = Sha256::hash(&[foo_hash, bar_hash].concat());
+
+        let tree = MerkleTree::<Sha256>::build_from(vec!["foo".into(), "bar".into()]);
+        let mut tree = tree.unwrap();
+
+        assert_eq![tree.count_leaves(), 2];
+        assert_eq![tree.get_root(), root];
+    }
+
+    #[test]
+    fn disk_db_round_trip_preserves_root_and_leaves() {
+        // A naturally 4-leaf tree, so `load`'s reconstructed leaves (which
+        // can't tell a padding duplicate from a real leaf) match `tree.leaves`
+        // exactly instead of also picking up a trailing duplicate.
+        let dir = std::env::temp_dir().join("rusty_merkle_tree_disk_round_trip_test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = DiskDb::new(&dir).unwrap();
+        let mut tree: MerkleTree<Keccak256, DiskDb> = MerkleTree::new(db);
+        tree.add_leaves(vec!["foo".into(), "bar".into(), "hello".into(), "world!".into()]);
+        let root = tree.get_root();
+
+        let reopened_db = DiskDb::new(&dir).unwrap();
+        let reopened = MerkleTree::<Keccak256, DiskDb>::load(reopened_db, root).unwrap();
+
+        assert_eq!(reopened.count_leaves(), 4);
+        assert_eq!(reopened.leaves, tree.leaves);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn load_fails_when_a_node_is_missing() {
+        let dir = std::env::temp_dir().join("rusty_merkle_tree_disk_missing_node_test");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let db = DiskDb::new(&dir).unwrap();
+        let mut tree: MerkleTree<Keccak256, DiskDb> = MerkleTree::new(db);
+        tree.add_leaves(vec!["foo".into(), "bar".into()]);
+        let root = tree.get_root();
+
+        let foo_hash = calculate_hash("foo".as_bytes());
+        std::fs::remove_file(dir.join(hex::encode(&foo_hash))).unwrap();
+
+        let reopened_db = DiskDb::new(&dir).unwrap();
+        assert!(MerkleTree::<Keccak256, DiskDb>::load(reopened_db, root).is_none());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
 }
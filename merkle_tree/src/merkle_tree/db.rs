@@ -0,0 +1,131 @@
+use super::hex;
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Distinguishes a leaf node from an internal node in storage, so a reader
+/// walking the tree back from a root hash doesn't have to guess the shape
+/// of whatever `bytes` it gets back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeType {
+    Leaf,
+    Internal,
+}
+
+/// Content-addressed storage for tree nodes, keyed by the node's own hash.
+/// Because the key *is* the hash, two identical subtrees are always stored
+/// once, however many times they occur in the tree.
+pub trait Db {
+    fn insert(&mut self, key: [u8; 32], node_type: NodeType, payload_length: usize, bytes: &[u8]);
+    fn get(&self, key: &[u8; 32]) -> Option<(NodeType, usize, Vec<u8>)>;
+}
+
+/// In-memory `Db`, the default backend. Data does not survive a restart.
+#[derive(Debug, Default, PartialEq)]
+pub struct MemoryDb {
+    nodes: HashMap<[u8; 32], (NodeType, usize, Vec<u8>)>,
+}
+
+impl MemoryDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Db for MemoryDb {
+    fn insert(&mut self, key: [u8; 32], node_type: NodeType, payload_length: usize, bytes: &[u8]) {
+        self.nodes.insert(key, (node_type, payload_length, bytes.to_vec()));
+    }
+
+    fn get(&self, key: &[u8; 32]) -> Option<(NodeType, usize, Vec<u8>)> {
+        self.nodes.get(key).cloned()
+    }
+}
+
+/// Disk-backed `Db` that persists each node as its own file, named after its
+/// hex-encoded hash, under `root_dir`. Lets a tree's nodes survive a restart,
+/// at the cost of a syscall per node. `MerkleTree` still keeps all of its
+/// leaves and levels in memory as well, so this does not reduce its RAM use.
+pub struct DiskDb {
+    root_dir: PathBuf,
+}
+
+impl DiskDb {
+    pub fn new(root_dir: impl AsRef<Path>) -> io::Result<Self> {
+        let root_dir = root_dir.as_ref().to_path_buf();
+        fs::create_dir_all(&root_dir)?;
+        Ok(Self { root_dir })
+    }
+
+    fn path_for(&self, key: &[u8; 32]) -> PathBuf {
+        self.root_dir.join(hex::encode(key))
+    }
+}
+
+impl Db for DiskDb {
+    fn insert(&mut self, key: [u8; 32], node_type: NodeType, payload_length: usize, bytes: &[u8]) {
+        let mut record = Vec::with_capacity(9 + bytes.len());
+        record.push(node_type as u8);
+        record.extend_from_slice(&(payload_length as u64).to_le_bytes());
+        record.extend_from_slice(bytes);
+        // Best-effort: a failed write is ignored, and a later `get` of this
+        // key returns `None`, so `MerkleTree::load` of any root above it fails.
+        let _ = fs::write(self.path_for(&key), record);
+    }
+
+    fn get(&self, key: &[u8; 32]) -> Option<(NodeType, usize, Vec<u8>)> {
+        let record = fs::read(self.path_for(key)).ok()?;
+        if record.len() < 9 {
+            return None;
+        }
+        let node_type = match record[0] {
+            0 => NodeType::Leaf,
+            _ => NodeType::Internal,
+        };
+        let payload_length = u64::from_le_bytes(record[1..9].try_into().ok()?) as usize;
+        Some((node_type, payload_length, record[9..].to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_db_round_trips_a_node() {
+        let mut db = MemoryDb::new();
+        db.insert([1; 32], NodeType::Leaf, 32, &[1; 32]);
+        assert_eq!(db.get(&[1; 32]), Some((NodeType::Leaf, 32, vec![1; 32])));
+    }
+
+    #[test]
+    fn memory_db_missing_key_is_none() {
+        let db = MemoryDb::new();
+        assert_eq!(db.get(&[0; 32]), None);
+    }
+
+    #[test]
+    fn disk_db_round_trips_a_node() {
+        let dir = std::env::temp_dir().join("rusty_merkle_tree_db_rs_round_trip_test");
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut db = DiskDb::new(&dir).unwrap();
+        db.insert([2; 32], NodeType::Internal, 64, &[7; 64]);
+        assert_eq!(db.get(&[2; 32]), Some((NodeType::Internal, 64, vec![7; 64])));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn disk_db_missing_key_is_none() {
+        let dir = std::env::temp_dir().join("rusty_merkle_tree_db_rs_missing_key_test");
+        let _ = fs::remove_dir_all(&dir);
+
+        let db = DiskDb::new(&dir).unwrap();
+        assert_eq!(db.get(&[0; 32]), None);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
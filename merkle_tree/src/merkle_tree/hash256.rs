@@ -0,0 +1,101 @@
+use super::hex;
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::convert::TryInto;
+use std::fmt;
+
+/// Why a `Hash256` could not be parsed from a string.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    InvalidCharacter,
+    InvalidLength,
+}
+
+/// A 32-byte hash with readable (de)serialization, so roots and proofs can
+/// be printed, transmitted as JSON, and parsed back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Hash256([u8; 32]);
+
+impl Hash256 {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let array: [u8; 32] = bytes.try_into().map_err(|_| ParseError::InvalidLength)?;
+        Ok(Hash256(array))
+    }
+
+    pub fn from_hex(input: &str) -> Result<Self, ParseError> {
+        if input.len() != 64 {
+            return Err(ParseError::InvalidLength);
+        }
+        if !input.is_ascii() {
+            return Err(ParseError::InvalidCharacter);
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&input[i * 2..i * 2 + 2], 16)
+                .map_err(|_| ParseError::InvalidCharacter)?;
+        }
+        Ok(Hash256(bytes))
+    }
+
+    pub fn from_base64(input: &str) -> Result<Self, ParseError> {
+        let bytes = base64::decode(input).map_err(|_| ParseError::InvalidCharacter)?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    pub fn to_base64(&self) -> String {
+        base64::encode(&self.0)
+    }
+
+    pub fn as_bytes(&self) -> [u8; 32] {
+        self.0
+    }
+}
+
+impl From<[u8; 32]> for Hash256 {
+    fn from(bytes: [u8; 32]) -> Self {
+        Hash256(bytes)
+    }
+}
+
+impl From<Hash256> for [u8; 32] {
+    fn from(hash: Hash256) -> Self {
+        hash.0
+    }
+}
+
+impl fmt::Display for Hash256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_hex())
+    }
+}
+
+impl Serialize for Hash256 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Hash256 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Hash256::from_hex(&value).map_err(|err| D::Error::custom(format!("{:?}", err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hex_rejects_non_ascii_instead_of_panicking() {
+        // "é" is a 2-byte UTF-8 char starting at an odd byte offset, so the
+        // fixed 2-byte slicing in `from_hex` would otherwise cut it in half.
+        let input = format!("a\u{e9}{}", "a".repeat(61));
+        assert_eq!(input.len(), 64);
+        assert_eq!(Hash256::from_hex(&input), Err(ParseError::InvalidCharacter));
+    }
+}
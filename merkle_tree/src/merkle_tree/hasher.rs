@@ -0,0 +1,40 @@
+use crypto::digest::Digest;
+use crypto::sha2::Sha256 as Sha256Digest;
+use crypto::sha3::Sha3;
+
+/// A pluggable digest function used to hash leaves and internal nodes.
+///
+/// Implementors must always produce a fixed 32-byte output so hashes can be
+/// stored and compared uniformly regardless of the underlying algorithm.
+pub trait Hasher {
+    fn hash(input: &[u8]) -> [u8; 32];
+}
+
+/// Keccak-256, the digest used by Ethereum. This is the default `Hasher` for
+/// `MerkleTree` so existing callers keep their current behavior.
+#[derive(Debug, PartialEq)]
+pub struct Keccak256;
+
+impl Hasher for Keccak256 {
+    fn hash(input: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha3::keccak256();
+        hasher.input(input);
+        let mut output = [0; 32];
+        hasher.result(&mut output);
+        output
+    }
+}
+
+/// SHA-256, the digest used by Bitcoin.
+#[derive(Debug, PartialEq)]
+pub struct Sha256;
+
+impl Hasher for Sha256 {
+    fn hash(input: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha256Digest::new();
+        hasher.input(input);
+        let mut output = [0; 32];
+        hasher.result(&mut output);
+        output
+    }
+}
@@ -0,0 +1,5 @@
+/// Lower-case hex encoding shared by anything that needs to render a hash as
+/// a human-readable string (`Hash256::to_hex`, on-disk file names, ...).
+pub(crate) fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
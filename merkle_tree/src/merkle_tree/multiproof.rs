@@ -0,0 +1,106 @@
+use super::hash256::Hash256;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A proof that several leaves, all at once, belong to the same tree: only
+/// the sibling hashes that can't be derived from the requested leaves or
+/// from each other, deduplicated across the whole batch.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MultiProof {
+    pub leaf_indices: Vec<usize>,
+    pub siblings: Vec<Hash256>,
+    /// The padded leaf-layer width, so the verifier knows how many levels to replay.
+    pub tree_width: usize,
+}
+
+impl MultiProof {
+    /// Walks the tree bottom-up from `leaf_indices`, emitting one sibling
+    /// hash per pair where exactly one side is already known (requested, or
+    /// computed from a lower level), in ascending pair order.
+    pub(super) fn build<F: Fn(&[u8], &[u8]) -> [u8; 32]>(
+        leaves: &[[u8; 32]],
+        leaf_indices: &[usize],
+        hash: F,
+    ) -> MultiProof {
+        let mut level_hashes = leaves.to_vec();
+        let mut known: BTreeSet<usize> = leaf_indices.iter().copied().collect();
+        let mut siblings = vec![];
+
+        while level_hashes.len() > 1 {
+            let mut next_known = BTreeSet::new();
+            let mut processed = BTreeSet::new();
+            for &index in &known {
+                let pair = index / 2;
+                if !processed.insert(pair) {
+                    continue;
+                }
+                let (left, right) = (pair * 2, pair * 2 + 1);
+                if !known.contains(&left) {
+                    siblings.push(level_hashes[left]);
+                }
+                if !known.contains(&right) {
+                    siblings.push(level_hashes[right]);
+                }
+                next_known.insert(pair);
+            }
+
+            let mut parents = vec![];
+            for chunk in level_hashes.chunks(2) {
+                parents.push(hash(&chunk[0], &chunk[1]));
+            }
+            level_hashes = parents;
+            known = next_known;
+        }
+
+        MultiProof {
+            leaf_indices: leaf_indices.to_vec(),
+            siblings: siblings.into_iter().map(Hash256::from).collect(),
+            tree_width: leaves.len(),
+        }
+    }
+
+    /// Replays the proof against `leaves` (the claimed index -> hash pairs
+    /// being proven), consuming `siblings` in the same deterministic order
+    /// `build` produced them in.
+    pub(super) fn replay<F: Fn(&[u8], &[u8]) -> [u8; 32]>(
+        &self,
+        leaves: &[(usize, [u8; 32])],
+        hash: F,
+    ) -> Option<[u8; 32]> {
+        let mut known: BTreeMap<usize, [u8; 32]> = leaves.iter().copied().collect();
+        if known.len() != leaves.len() || known.len() != self.leaf_indices.len() {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut width = self.tree_width;
+
+        while width > 1 {
+            let mut next_known = BTreeMap::new();
+            let mut processed = BTreeSet::new();
+            let indices: Vec<usize> = known.keys().copied().collect();
+            for index in indices {
+                let pair = index / 2;
+                if !processed.insert(pair) {
+                    continue;
+                }
+                let (left_index, right_index) = (pair * 2, pair * 2 + 1);
+                let left = match known.get(&left_index) {
+                    Some(&known_hash) => known_hash,
+                    None => siblings.next()?.as_bytes(),
+                };
+                let right = match known.get(&right_index) {
+                    Some(&known_hash) => known_hash,
+                    None => siblings.next()?.as_bytes(),
+                };
+                next_known.insert(pair, hash(&left, &right));
+            }
+            known = next_known;
+            width = (width + 1) / 2;
+        }
+
+        if siblings.next().is_some() {
+            return None;
+        }
+        known.get(&0).copied()
+    }
+}
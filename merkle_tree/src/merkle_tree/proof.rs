@@ -0,0 +1,24 @@
+use super::hash256::Hash256;
+use serde::{Deserialize, Serialize};
+
+/// A Merkle inclusion proof: the sibling hashes from a leaf up to the root,
+/// plus the leaf's index, bundled so it can be serialized and sent to a
+/// client that only holds the root.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Proof {
+    pub siblings: Vec<Hash256>,
+    pub leaf_index: usize,
+}
+
+impl Proof {
+    pub fn new(siblings: Vec<[u8; 32]>, leaf_index: usize) -> Self {
+        Self {
+            siblings: siblings.into_iter().map(Hash256::from).collect(),
+            leaf_index,
+        }
+    }
+
+    pub fn siblings(&self) -> Vec<[u8; 32]> {
+        self.siblings.iter().map(|hash| hash.as_bytes()).collect()
+    }
+}
@@ -0,0 +1,293 @@
+use super::hasher::{Hasher, Keccak256};
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+/// The hash every empty subtree collapses to, at any depth. Internal nodes
+/// whose two children are both `EMPTY_NODE` are never actually hashed or
+/// stored; they're just `EMPTY_NODE` again.
+pub const EMPTY_NODE: [u8; 32] = [0; 32];
+
+/// What a proof's path terminates in: nothing, a leaf for a different key
+/// (both prove non-membership), or the leaf for the queried key itself
+/// (proves membership).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Terminal {
+    Empty,
+    OtherLeaf { key_hash: [u8; 32], leaf_hash: [u8; 32] },
+    SameLeaf { leaf_hash: [u8; 32] },
+}
+
+/// A sibling hash per level plus how the queried key's path terminates,
+/// enough to replay the root in `SparseMerkleTree::verify`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseProof {
+    pub siblings: Vec<[u8; 32]>,
+    pub terminal: Terminal,
+}
+
+/// `add` failed because another key already occupies this key's leaf slot.
+///
+/// At `num_levels` bits of routing, two distinct keys can share a path long
+/// before their full hashes diverge; a fixed-depth tree has nowhere to push
+/// the collision down to, so it's reported instead of silently overwriting
+/// the existing key's data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyCollision {
+    pub existing_key_hash: [u8; 32],
+}
+
+/// A fixed-depth sparse Merkle tree: values are routed to a leaf slot by the
+/// bits of their key's hash, so lookup and proof generation are O(num_levels)
+/// regardless of how many keys have been added. Unlike `MerkleTree`, a proof
+/// here can also show that a key is *absent*: the path to its slot either
+/// ends in `EMPTY_NODE` or in a leaf that belongs to a different key.
+pub struct SparseMerkleTree<H: Hasher = Keccak256> {
+    num_levels: usize,
+    // Sparse: only non-empty nodes are stored, keyed by (level, path prefix
+    // from the root). Level `num_levels` holds the leaves.
+    nodes: HashMap<(usize, Vec<bool>), [u8; 32]>,
+    // A leaf slot's hash alone can't distinguish "empty" from "occupied by
+    // some other key" once num_levels is smaller than a full hash width, so
+    // track who currently occupies each populated slot.
+    occupants: HashMap<Vec<bool>, [u8; 32]>,
+    _hasher: PhantomData<H>,
+}
+
+impl<H: Hasher> SparseMerkleTree<H> {
+    pub fn new(num_levels: usize) -> Self {
+        Self {
+            num_levels,
+            nodes: HashMap::new(),
+            occupants: HashMap::new(),
+            _hasher: PhantomData,
+        }
+    }
+
+    /// The first `num_levels` bits of `key_hash`, LSB-first, used to route a
+    /// key to its leaf slot one bit per level.
+    pub fn get_path(num_levels: usize, key_hash: [u8; 32]) -> Vec<bool> {
+        (0..num_levels)
+            .map(|i| (key_hash[31 - i / 8] >> (i % 8)) & 1 == 1)
+            .collect()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        self.get_node(0, &[])
+    }
+
+    /// Adds `key` -> `value`, or fails with `KeyCollision` if a different
+    /// key already occupies this key's leaf slot.
+    pub fn add(&mut self, key: &[u8], value: &[u8]) -> Result<(), KeyCollision> {
+        let key_hash = H::hash(key);
+        let path = Self::get_path(self.num_levels, key_hash);
+        if let Some(&existing_key_hash) = self.occupants.get(&path) {
+            if existing_key_hash != key_hash {
+                return Err(KeyCollision { existing_key_hash });
+            }
+        }
+        let leaf_hash = Self::leaf_hash(key_hash, value);
+        self.occupants.insert(path.clone(), key_hash);
+        self.set_node(self.num_levels, &path, leaf_hash);
+        self.recompute_ancestors(&path);
+        Ok(())
+    }
+
+    /// Builds the sibling list and terminal for `key`'s path, whether or not
+    /// `key` is actually present.
+    pub fn prove(&self, key: &[u8]) -> SparseProof {
+        let key_hash = H::hash(key);
+        let path = Self::get_path(self.num_levels, key_hash);
+        let siblings = (0..self.num_levels)
+            .map(|level| {
+                let mut sibling_prefix = path[..level].to_vec();
+                sibling_prefix.push(!path[level]);
+                self.get_node(level + 1, &sibling_prefix)
+            })
+            .collect();
+        let leaf_hash = self.get_node(self.num_levels, &path);
+        let terminal = match self.occupants.get(&path) {
+            None => Terminal::Empty,
+            Some(&occupant) if occupant == key_hash => Terminal::SameLeaf { leaf_hash },
+            Some(&occupant) => Terminal::OtherLeaf { key_hash: occupant, leaf_hash },
+        };
+        SparseProof { siblings, terminal }
+    }
+
+    /// A proof that `key` is absent, or `None` if `key` is actually present.
+    pub fn prove_absence(&self, key: &[u8]) -> Option<SparseProof> {
+        let proof = self.prove(key);
+        match proof.terminal {
+            Terminal::Empty | Terminal::OtherLeaf { .. } => Some(proof),
+            Terminal::SameLeaf { .. } => None,
+        }
+    }
+
+    /// Replays `proof` from its terminal up to the root, checking it against
+    /// `root`. Pass `value = Some(..)` to check membership (the terminal must
+    /// be the queried key's own leaf, for that exact value); pass `None` to
+    /// check non-membership (the terminal must be empty or another key's leaf).
+    pub fn verify(
+        num_levels: usize,
+        key: &[u8],
+        value: Option<&[u8]>,
+        proof: &SparseProof,
+        root: [u8; 32],
+    ) -> bool {
+        if proof.siblings.len() != num_levels {
+            return false;
+        }
+        let key_hash = H::hash(key);
+        let path = Self::get_path(num_levels, key_hash);
+
+        let mut hash = match (&proof.terminal, value) {
+            (Terminal::SameLeaf { leaf_hash }, Some(value)) => {
+                if *leaf_hash != Self::leaf_hash(key_hash, value) {
+                    return false;
+                }
+                *leaf_hash
+            }
+            (Terminal::Empty, None) => EMPTY_NODE,
+            (Terminal::OtherLeaf { key_hash: occupant, leaf_hash }, None) => {
+                if *occupant == key_hash {
+                    return false;
+                }
+                *leaf_hash
+            }
+            _ => return false,
+        };
+
+        for level in (0..num_levels).rev() {
+            let sibling = proof.siblings[level];
+            hash = if path[level] {
+                Self::combine(sibling, hash)
+            } else {
+                Self::combine(hash, sibling)
+            };
+        }
+        hash == root
+    }
+
+    fn recompute_ancestors(&mut self, path: &[bool]) {
+        for level in (0..self.num_levels).rev() {
+            let prefix = &path[..level];
+            let mut left_key = prefix.to_vec();
+            left_key.push(false);
+            let mut right_key = prefix.to_vec();
+            right_key.push(true);
+            let left = self.get_node(level + 1, &left_key);
+            let right = self.get_node(level + 1, &right_key);
+            self.set_node(level, prefix, Self::combine(left, right));
+        }
+    }
+
+    fn get_node(&self, level: usize, prefix: &[bool]) -> [u8; 32] {
+        self.nodes
+            .get(&(level, prefix.to_vec()))
+            .copied()
+            .unwrap_or(EMPTY_NODE)
+    }
+
+    fn set_node(&mut self, level: usize, prefix: &[bool], hash: [u8; 32]) {
+        if hash == EMPTY_NODE {
+            self.nodes.remove(&(level, prefix.to_vec()));
+        } else {
+            self.nodes.insert((level, prefix.to_vec()), hash);
+        }
+    }
+
+    fn leaf_hash(key_hash: [u8; 32], value: &[u8]) -> [u8; 32] {
+        H::hash(&[key_hash.to_vec(), H::hash(value).to_vec()].concat())
+    }
+
+    fn combine(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+        if left == EMPTY_NODE && right == EMPTY_NODE {
+            EMPTY_NODE
+        } else {
+            H::hash(&[left, right].concat())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_tree_root_is_empty_node() {
+        let tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(8);
+        assert_eq!(tree.root(), EMPTY_NODE);
+    }
+
+    #[test]
+    fn membership_proof_verifies() {
+        let mut tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(8);
+        tree.add(b"foo", b"bar").unwrap();
+
+        let proof = tree.prove(b"foo");
+        assert!(SparseMerkleTree::<Keccak256>::verify(
+            8,
+            b"foo",
+            Some(b"bar"),
+            &proof,
+            tree.root()
+        ));
+    }
+
+    #[test]
+    fn absence_proof_for_untouched_key_verifies() {
+        let mut tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(8);
+        tree.add(b"foo", b"bar").unwrap();
+
+        let proof = tree.prove_absence(b"missing").unwrap();
+        assert!(SparseMerkleTree::<Keccak256>::verify(
+            8,
+            b"missing",
+            None,
+            &proof,
+            tree.root()
+        ));
+    }
+
+    #[test]
+    fn present_key_has_no_absence_proof() {
+        let mut tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(8);
+        tree.add(b"foo", b"bar").unwrap();
+
+        assert!(tree.prove_absence(b"foo").is_none());
+    }
+
+    #[test]
+    fn membership_proof_rejects_wrong_value() {
+        let mut tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(8);
+        tree.add(b"foo", b"bar").unwrap();
+
+        let proof = tree.prove(b"foo");
+        assert!(!SparseMerkleTree::<Keccak256>::verify(
+            8,
+            b"foo",
+            Some(b"wrong"),
+            &proof,
+            tree.root()
+        ));
+    }
+
+    #[test]
+    fn add_reports_collision_instead_of_overwriting() {
+        // "key4" and "key36" hash to the same 4-bit path.
+        let mut tree: SparseMerkleTree<Keccak256> = SparseMerkleTree::new(4);
+        tree.add(b"key4", b"first").unwrap();
+
+        let collision = tree.add(b"key36", b"second").unwrap_err();
+        assert_eq!(collision.existing_key_hash, Keccak256::hash(b"key4"));
+
+        // The first key's data must still be intact.
+        let proof = tree.prove(b"key4");
+        assert!(SparseMerkleTree::<Keccak256>::verify(
+            4,
+            b"key4",
+            Some(b"first"),
+            &proof,
+            tree.root()
+        ));
+    }
+}